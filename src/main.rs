@@ -1,17 +1,15 @@
 mod signals;
+mod transport;
 
 use std::cell::Cell;
 use std::env::args;
 use std::fmt::Display;
-use std::fs::remove_file;
 use std::io::{self, PipeReader, Read, Write, pipe, stdout};
-use std::os::fd::AsRawFd;
-use std::os::unix::net::{UnixListener, UnixStream};
 use std::process::{ChildStderr, ChildStdin, ChildStdout, Command, Stdio, exit};
 use std::thread::spawn;
 
-use epoll::{ControlOptions, Event, Events};
 use signals::kill_child_on_signal;
+use transport::{Interest, PlatformTransport, Registrable, Selector, Transport, TransportListener, TransportStream};
 
 /// A buffer that contains 2N bytes, which allows a writer to write N bytes at a
 /// time, and a reader to read N bytes at a time.
@@ -111,6 +109,21 @@ impl<T: Default + Copy, const N: usize> FlipBuffer<T, N> {
             Err(err) => Err(err)
         }
     }
+
+    /// Returns true if there is data that [`with_read`](Self::with_read)
+    /// could hand to a consumer right now, without waiting for more to be
+    /// written first.
+    pub fn has_readable(&self) -> bool {
+        self.read_cursor < self.read_max || self.write_cursor > 0
+    }
+
+    /// Returns true if [`with_write`](Self::with_write) could currently hand
+    /// a consumer a non-empty slice to write into. False only while the
+    /// write page is full and the read page still holds undrained data,
+    /// i.e. a flip wouldn't free anything up either.
+    pub fn has_writable(&self) -> bool {
+        self.write_cursor < N || self.read_cursor == self.read_max
+    }
 }
 
 /// Prints `msg` to stderr and exits the process.
@@ -121,87 +134,468 @@ fn die<D: Display>(msg: D) -> ! {
 
 /// Displays the command-line help and exits the process.
 fn usage() -> ! {
-    die("Usage: iosock SOCKET_PATH PROGRAM ARGS...")
+    die("Usage: iosock [--framed] SOCKET_PATH PROGRAM ARGS...")
+}
+
+/// Puts `fd` into non-blocking mode, so that reads and writes that can't
+/// make progress immediately return [`io::ErrorKind::WouldBlock`] instead of
+/// blocking the calling thread.
+#[cfg(unix)]
+fn set_nonblocking<F: std::os::fd::AsRawFd>(fd: &F) -> io::Result<()> {
+    let raw = fd.as_raw_fd();
+    let flags = unsafe { libc::fcntl(raw, libc::F_GETFL) };
+    if flags < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    if unsafe { libc::fcntl(raw, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// `Stdio::piped()` hands back plain (non-overlapped) pipes on Windows, so
+/// there's no flag to flip here; making the child side non-blocking too is
+/// follow-up work alongside the rest of the overlapped child I/O in
+/// `transport::windows`.
+#[cfg(windows)]
+fn set_nonblocking<F>(_fd: &F) -> io::Result<()> {
+    Ok(())
+}
+
+/// Runs `op`, treating a [`io::ErrorKind::WouldBlock`] error as "zero bytes
+/// moved" rather than a hard failure, since the fd is non-blocking and
+/// simply has nothing to offer right now.
+fn nonblocking<F: FnOnce() -> io::Result<usize>>(op: F) -> io::Result<usize> {
+    match op() {
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(0),
+        result => result,
+    }
+}
+
+/// How many consecutive would-block writes a client can rack up while it has
+/// pending output before it's dropped for stalling the fan-out.
+const MAX_CLIENT_STALLED_WRITES: u32 = 256;
+
+/// How large a single client's `pending` output backlog is allowed to grow
+/// before that client is dropped outright, independent of
+/// `MAX_CLIENT_STALLED_WRITES`. A client can accumulate up to `BUFFER_BYTES`
+/// of new output per stalled tick, so bounding eviction purely by a count of
+/// stalled writes still lets `pending` grow to multiples of `BUFFER_BYTES`
+/// before it's caught; this keeps it to the "small buffer" the per-client
+/// backlog is meant to be.
+const MAX_CLIENT_PENDING_BYTES: usize = 4 * BUFFER_BYTES;
+
+/// Size of the shared buffers used to move bytes between the socket clients
+/// and the child process in each direction.
+const BUFFER_BYTES: usize = 32 * 1024;
+
+/// In `--framed` mode, how large a single client's undecoded `input` backlog
+/// is allowed to grow before the bridge stops reading from that client's
+/// socket. Without this, a client sending frames faster than a slow child
+/// drains its stdin would otherwise have `input` grow without bound, since
+/// frame headers and payload only leave it as fast as `child_stdin_buf` has
+/// spare capacity.
+const MAX_CLIENT_INPUT_BACKLOG: usize = BUFFER_BYTES;
+
+/// One socket client connected to the bridge.
+struct Client<S: TransportStream> {
+    stream: S,
+    /// Bytes broadcast from `child_stdouterr_buf` that this client hasn't
+    /// caught up on yet, because it drains slower than its siblings. Meant
+    /// to stay a small buffer: bounded by both `MAX_CLIENT_STALLED_WRITES`
+    /// and `MAX_CLIENT_PENDING_BYTES`, whichever trips first.
+    pending: Vec<u8>,
+    /// Consecutive would-block writes seen while `pending` was non-empty.
+    stalled_writes: u32,
+    /// Whether EPOLLOUT (or its platform equivalent) is currently armed for
+    /// this client.
+    write_armed: bool,
+    /// In `--framed` mode, bytes read off this client's own socket that
+    /// haven't yet been parsed into complete frames. Kept per-client so one
+    /// client's partial frame can never be completed with bytes that
+    /// actually came from another client.
+    input: Vec<u8>,
+    /// In `--framed` mode, this client's own frame-parsing state, run only
+    /// over `input`.
+    frame: FrameWriter,
+}
+
+impl<S: TransportStream> Client<S> {
+    fn interest(&self) -> Interest {
+        Interest::READABLE.with_writable(!self.pending.is_empty())
+    }
+}
+
+/// In `--framed` mode, tracks how much of the current length-prefixed
+/// frame's payload is still owed to the child, so a copy that only makes
+/// partial progress (because the shared sink buffer filled up) doesn't get
+/// replayed from the start next time.
+///
+/// Each connected client gets its own `FrameWriter` and runs it only over
+/// bytes read from that client's own socket; sharing one instance across
+/// clients would interleave their frame headers and payloads into garbage.
+struct FrameWriter {
+    pending_payload: usize,
+}
+
+impl FrameWriter {
+    fn new() -> Self {
+        FrameWriter { pending_payload: 0 }
+    }
+
+    /// Parses length-prefixed frames off the front of `buf` and copies their
+    /// payload bytes into `sink`, stopping as soon as `sink` runs out of
+    /// room or `buf` runs out. Payload bytes are copied as they arrive: a
+    /// frame's payload is not held back until the whole frame is present,
+    /// so the child can receive one frame's payload across several writes.
+    ///
+    /// Returns the number of bytes consumed from `buf`. A header is only
+    /// ever counted as consumed once its length has been recorded in
+    /// `pending_payload`, so the caller never needs to see it again; only a
+    /// trailing partial header is left unconsumed for the next call.
+    fn drain(&mut self, buf: &[u8], sink: &mut FlipBuffer<u8, BUFFER_BYTES>) -> io::Result<usize> {
+        let mut pos = 0;
+        loop {
+            if self.pending_payload == 0 {
+                if buf.len() - pos < 4 {
+                    break;
+                }
+                let header: [u8; 4] = buf[pos..pos + 4].try_into().unwrap();
+                self.pending_payload = u32::from_be_bytes(header) as usize;
+                pos += 4;
+                continue;
+            }
+            let avail = (buf.len() - pos).min(self.pending_payload);
+            if avail == 0 {
+                break;
+            }
+            let written = sink.with_write(|dst| {
+                let n = dst.len().min(avail);
+                dst[..n].copy_from_slice(&buf[pos..pos + n]);
+                Ok::<usize, io::Error>(n)
+            })?;
+            pos += written;
+            self.pending_payload -= written;
+            if written < avail {
+                break;
+            }
+        }
+        Ok(pos)
+    }
 }
 
-/// Registers `target` with the epoll socket `pollster`, configured to trigger
-/// when the provided `events` occur. The data for the even is `target` as a
-/// file descriptor.
-fn epoll_add<P: AsRawFd, T: AsRawFd>(pollster: &P, target: &T, events: Events) -> io::Result<()> {
-    let target_fd = target.as_raw_fd();
-    epoll::ctl(
-        pollster.as_raw_fd(),
-        ControlOptions::EPOLL_CTL_ADD,
-        target_fd,
-        Event::new(events, target_fd as u64),
-    )
+/// Appends `buf` to every client's pending output, wrapping it in its own
+/// 4-byte big-endian length prefix first when `framed` is set.
+fn broadcast_chunk<S: TransportStream>(clients: &mut [Client<S>], buf: &[u8], framed: bool) {
+    if buf.is_empty() {
+        return;
+    }
+    if framed {
+        let header = (buf.len() as u32).to_be_bytes();
+        for client in clients.iter_mut() {
+            client.pending.extend_from_slice(&header);
+            client.pending.extend_from_slice(buf);
+        }
+    } else {
+        for client in clients.iter_mut() {
+            client.pending.extend_from_slice(buf);
+        }
+    }
 }
 
-/// Accepts connections on the Unix socket `listener`, and passes data from it
-/// to `child_stdin`, and data from `child_stdout` and `child_stderr` to
-/// `listener`. Stops when data is received on the `child_notify` pipe.
+/// Accepts connections on `listener`, merges input from all of them into
+/// `child_stdin`, and broadcasts data from `child_stdout` and `child_stderr`
+/// to every connected client. Stops when data is received on the
+/// `child_notify` pipe.
 ///
-/// When there is no client on `listener` the data from the child process is
+/// When there are no clients connected the data from the child process is
 /// written to the stdout and stderr of this process.
-fn socket_stream_bridge(
-    listener: UnixListener,
+///
+/// When `framed` is set, socket traffic in both directions is a sequence of
+/// 4-byte big-endian length prefixes followed by that many payload bytes,
+/// instead of a raw byte stream; the no-client stdout fallback is left
+/// un-prefixed either way.
+fn socket_stream_bridge<T: Transport>(
+    listener: T::Listener,
     mut child_stdin: ChildStdin,
     mut child_stdout: ChildStdout,
     mut child_stderr: ChildStderr,
     child_notify: PipeReader,
+    framed: bool,
 ) -> io::Result<()> {
-    let pollster = epoll::create(true)?;
-    let mut socket_client: Option<UnixStream> = None;
-
-    epoll_add(&pollster, &child_stdout, Events::EPOLLIN)?;
-    epoll_add(&pollster, &child_stderr, Events::EPOLLIN)?;
-    epoll_add(&pollster, &listener, Events::EPOLLIN)?;
-    epoll_add(&pollster, &child_notify, Events::EPOLLIN)?;
+    set_nonblocking(&child_stdin)?;
+    set_nonblocking(&child_stdout)?;
+    set_nonblocking(&child_stderr)?;
+
+    let pollster = T::Selector::create()?;
+    let mut clients: Vec<Client<T::Stream>> = Vec::new();
+
+    pollster.register(&child_stdout, Interest::READABLE)?;
+    pollster.register(&child_stderr, Interest::READABLE)?;
+    pollster.register(&listener, Interest::READABLE)?;
+    pollster.register(&child_notify, Interest::READABLE)?;
+    // child_stdin is never read from; it starts with no interest and only
+    // gets write interest while child_stdin_buf has something to drain into
+    // it.
+    pollster.register(&child_stdin, Interest::NONE)?;
 
     let mut stdout = stdout();
 
-    let child_stdout_fd = child_stdout.as_raw_fd();
-    let child_stderr_fd = child_stderr.as_raw_fd();
-    let listener_fd = listener.as_raw_fd();
-    let notify_fd = child_notify.as_raw_fd();
-
-    let mut events_buf = [Event::new(Events::empty(), 0); 8];
-
-    const BUFFER_BYTES: usize = 32 * 1024;
+    let child_stdout_token = child_stdout.token();
+    let child_stderr_token = child_stderr.token();
+    let child_stdin_token = child_stdin.token();
+    let listener_token = listener.token();
+    let notify_token = child_notify.token();
+
+    let mut events_buf = Vec::new();
+
+    // Shared across every connected client: all of their input is merged
+    // into this one buffer before being handed to the child (in `--framed`
+    // mode, only after each client's own bytes have already been decoded
+    // into plain payload by its own `FrameWriter` — see the client read
+    // branch below). That makes keeping has_writable()'s full-buffer check
+    // correct more important with fan-out than with a single client, since
+    // one slow child drain would otherwise look like every connected client
+    // hanging up at once.
     let mut child_stdin_buf: FlipBuffer<u8, BUFFER_BYTES> = FlipBuffer::new();
     let mut child_stdouterr_buf: FlipBuffer<u8, BUFFER_BYTES> = FlipBuffer::new();
 
+    // Whether write interest is currently armed for child_stdin, so we only
+    // call Selector::modify when the interest actually needs to change.
+    let mut child_stdin_write_armed = false;
+
+    let mut child_stdout_closed = false;
+    let mut child_stderr_closed = false;
+    let mut child_stdin_closed = false;
+
     'events: loop {
-        let events = epoll::wait(pollster, -1, &mut events_buf)?;
-        for i in 0..events {
-            let target = events_buf[i].data as i32;
-            if target == notify_fd {
+        events_buf.clear();
+        pollster.wait(&mut events_buf)?;
+        for event in &events_buf {
+            let target = event.token;
+            if target == notify_token {
                 break 'events
-            } else if target == child_stdout_fd {
-                child_stdouterr_buf.with_write(|buf| child_stdout.read(buf))?;
-            } else if target == child_stderr_fd {
-                child_stdouterr_buf.with_write(|buf| child_stderr.read(buf))?;
-            } else if target == listener_fd {
-                let (new_client, _) = listener.accept()?;
-                if socket_client.is_some() {
-                    drop(new_client);
+            } else if target == child_stdout_token {
+                child_stdouterr_buf.with_write(|buf| nonblocking(|| child_stdout.read(buf)))?;
+                if event.hangup {
+                    // Pick up any bytes still sitting in the pipe before we
+                    // stop polling it.
+                    while child_stdouterr_buf.with_write(|buf| nonblocking(|| child_stdout.read(buf)))? > 0 {}
+                    // If the buffer filled up first, keep polling: the hangup
+                    // is reported again once the drain below makes room.
+                    if child_stdouterr_buf.has_writable() {
+                        pollster.deregister(&child_stdout)?;
+                        child_stdout_closed = true;
+                    }
+                }
+            } else if target == child_stderr_token {
+                child_stdouterr_buf.with_write(|buf| nonblocking(|| child_stderr.read(buf)))?;
+                if event.hangup {
+                    while child_stdouterr_buf.with_write(|buf| nonblocking(|| child_stderr.read(buf)))? > 0 {}
+                    if child_stdouterr_buf.has_writable() {
+                        pollster.deregister(&child_stderr)?;
+                        child_stderr_closed = true;
+                    }
+                }
+            } else if target == child_stdin_token {
+                // Writability is handled by the drain below; nothing to do
+                // here beyond having woken up for it. But the kernel reports
+                // EPOLLHUP/EPOLLERR for a registered fd unconditionally, per
+                // epoll_ctl(2), regardless of requested interest — if the
+                // child closed its stdin while staying alive (e.g. it
+                // detaches from stdin entirely), that event fires on every
+                // wait() forever unless we stop polling this fd.
+                if event.hangup {
+                    pollster.deregister(&child_stdin)?;
+                    child_stdin_closed = true;
+                }
+            } else if target == listener_token {
+                match listener.accept() {
+                    Ok(new_client) => {
+                        pollster.register(&new_client, Interest::READABLE)?;
+                        clients.push(Client {
+                            stream: new_client,
+                            pending: Vec::new(),
+                            stalled_writes: 0,
+                            write_armed: false,
+                            input: Vec::new(),
+                            frame: FrameWriter::new(),
+                        });
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(err) => return Err(err),
+                }
+            } else if let Some(idx) = clients.iter().position(|c| c.stream.token() == target) {
+                if event.hangup {
+                    // Pick up anything still buffered on the socket, then
+                    // stop polling a client that's going away.
+                    if framed {
+                        let mut scratch = [0u8; 4096];
+                        loop {
+                            match nonblocking(|| clients[idx].stream.read(&mut scratch))? {
+                                0 => break,
+                                n => clients[idx].input.extend_from_slice(&scratch[..n]),
+                            }
+                        }
+                        let client = &mut clients[idx];
+                        let consumed = client.frame.drain(&client.input, &mut child_stdin_buf)?;
+                        client.input.drain(0..consumed);
+                    } else {
+                        while child_stdin_buf.with_write(|buf| nonblocking(|| clients[idx].stream.read(buf)))? > 0 {}
+                    }
+                    let client = clients.remove(idx);
+                    pollster.deregister(&client.stream)?;
+                } else if framed {
+                    // Read straight into this client's own backlog, never
+                    // into the shared child_stdin_buf: frame headers and
+                    // payload bytes from different clients must not end up
+                    // interleaved in one parse stream. The central drain
+                    // below runs each client's own FrameWriter only over
+                    // its own backlog.
+                    //
+                    // Bounded by MAX_CLIENT_INPUT_BACKLOG the same way the
+                    // unframed path is bounded by child_stdin_buf.has_writable():
+                    // if the backlog is already full, leave the bytes on the
+                    // socket. Epoll is level-triggered, so this client will
+                    // be reported readable again once the central drain
+                    // below frees up room.
+                    if clients[idx].input.len() < MAX_CLIENT_INPUT_BACKLOG {
+                        let mut scratch = [0u8; 4096];
+                        let mut would_block = false;
+                        let read = match clients[idx].stream.read(&mut scratch) {
+                            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                                would_block = true;
+                                0
+                            }
+                            Err(err) => return Err(err),
+                            Ok(n) => n,
+                        };
+                        clients[idx].input.extend_from_slice(&scratch[..read]);
+                        if read == 0 && !would_block {
+                            let client = clients.remove(idx);
+                            pollster.deregister(&client.stream)?;
+                        } else {
+                            clients[idx].stream.rearm();
+                        }
+                    }
+                } else if child_stdin_buf.has_writable() {
+                    let mut would_block = false;
+                    let copied = child_stdin_buf.with_write(|buf| match clients[idx].stream.read(buf) {
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            would_block = true;
+                            Ok(0)
+                        }
+                        result => result,
+                    })?;
+                    if copied == 0 && !would_block {
+                        let client = clients.remove(idx);
+                        pollster.deregister(&client.stream)?;
+                    } else {
+                        clients[idx].stream.rearm();
+                    }
+                }
+                // Else (unframed only): child_stdin_buf has no spare
+                // capacity right now. Leave the bytes on the socket; epoll
+                // is level-triggered so this client will be reported
+                // readable again once the drain below frees up room,
+                // instead of us reading a zero-length slice and mistaking a
+                // full buffer for a disconnect.
+            }
+        }
+
+        if child_stdout_closed && child_stderr_closed {
+            // Nothing left to ever produce more child output; push out
+            // whatever is still buffered and stop instead of blocking on a
+            // wait that nothing will ever wake for the child side.
+            while child_stdouterr_buf.has_readable() {
+                let drained = if clients.is_empty() {
+                    child_stdouterr_buf.with_read(|buf| nonblocking(|| stdout.write(buf)))?
                 } else {
-                    epoll_add(&pollster, &new_client, Events::EPOLLIN)?;
-                    socket_client = Some(new_client);
+                    child_stdouterr_buf.with_read(|buf| -> io::Result<usize> {
+                        broadcast_chunk(&mut clients, buf, framed);
+                        Ok(buf.len())
+                    })?
+                };
+                if drained == 0 {
+                    break;
+                }
+            }
+            for client in clients.iter_mut() {
+                let _ = nonblocking(|| client.stream.write(&client.pending));
+            }
+            break 'events;
+        }
+
+        if framed {
+            // Each client's own FrameWriter runs only over that client's own
+            // backlog, so frames from different clients can never merge
+            // into one corrupted parse stream. This also keeps retrying a
+            // backlog left over from a round where child_stdin_buf was
+            // full, even without a fresh readable event on that client.
+            for client in clients.iter_mut() {
+                if client.input.is_empty() {
+                    continue;
                 }
-            } else if let Some(client) = socket_client.as_mut() {
-                let copied = child_stdin_buf.with_write(|buf| client.read(buf))?;
-                if copied == 0 {
-                    socket_client = None;
+                let consumed = client.frame.drain(&client.input, &mut child_stdin_buf)?;
+                client.input.drain(0..consumed);
+            }
+        }
+        if !child_stdin_closed {
+            child_stdin_buf.with_read(|buf| nonblocking(|| child_stdin.write(buf)))?;
+        }
+
+        if clients.is_empty() {
+            child_stdouterr_buf.with_read(|buf| nonblocking(|| stdout.write(buf)))?;
+        } else {
+            child_stdouterr_buf.with_read(|buf| -> io::Result<usize> {
+                broadcast_chunk(&mut clients, buf, framed);
+                Ok(buf.len())
+            })?;
+        }
+
+        // Drain as much of each client's pending output as it'll currently
+        // accept, without letting a stalled client hold up the others.
+        let mut dead_clients = Vec::new();
+        for (idx, client) in clients.iter_mut().enumerate() {
+            if client.pending.is_empty() {
+                client.stalled_writes = 0;
+                continue;
+            }
+            let written = nonblocking(|| client.stream.write(&client.pending))?;
+            if written > 0 {
+                client.pending.drain(0..written);
+                client.stalled_writes = 0;
+            } else {
+                client.stalled_writes += 1;
+                if client.stalled_writes > MAX_CLIENT_STALLED_WRITES {
+                    dead_clients.push(idx);
                 }
             }
+            if client.pending.len() > MAX_CLIENT_PENDING_BYTES && !dead_clients.contains(&idx) {
+                dead_clients.push(idx);
+            }
+        }
+        for idx in dead_clients.into_iter().rev() {
+            let client = clients.remove(idx);
+            pollster.deregister(&client.stream)?;
         }
 
-        child_stdin_buf.with_read(|buf| child_stdin.write(buf))?;
-        match socket_client.as_mut() {
-            Some(client) => child_stdouterr_buf.with_read(|buf| client.write(buf))?,
-            None => child_stdouterr_buf.with_read(|buf| stdout.write(buf))?
-        };
+        if !child_stdin_closed {
+            let want_stdin_write = child_stdin_buf.has_readable();
+            if want_stdin_write != child_stdin_write_armed {
+                let interest = Interest::NONE.with_writable(want_stdin_write);
+                pollster.modify(&child_stdin, interest)?;
+                child_stdin_write_armed = want_stdin_write;
+            }
+        }
+
+        for client in clients.iter_mut() {
+            let want_client_write = !client.pending.is_empty();
+            if want_client_write != client.write_armed {
+                pollster.modify(&client.stream, client.interest())?;
+                client.write_armed = want_client_write;
+            }
+        }
     }
 
     Ok(())
@@ -210,13 +604,18 @@ fn socket_stream_bridge(
 fn main() {
     let mut args = args();
     args.next();
-    let sock_path = args.next().unwrap_or_else(|| usage());
+    let mut next_arg = args.next().unwrap_or_else(|| usage());
+    let framed = next_arg == "--framed";
+    if framed {
+        next_arg = args.next().unwrap_or_else(|| usage());
+    }
+    let sock_path = next_arg;
     let command_line: Vec<String> = args.collect();
     if command_line.len() == 0 {
         usage();
     }
 
-    let listener = UnixListener::bind(&sock_path)
+    let listener = PlatformTransport::bind(&sock_path)
         .unwrap_or_else(|err| die(format!("Could not create socket: {}", err)));
 
     let (closer_read, mut closer_write) = pipe()
@@ -238,7 +637,7 @@ fn main() {
     let child_stderr = child.stderr.take().unwrap_or_else(|| die("No stderr pipe for child"));
 
     let worker = spawn(move || {
-        socket_stream_bridge(listener, child_stdin, child_stdout, child_stderr, closer_read)
+        socket_stream_bridge::<PlatformTransport>(listener, child_stdin, child_stdout, child_stderr, closer_read, framed)
     });
 
     let wait_result = child.wait();
@@ -250,7 +649,7 @@ fn main() {
           .unwrap_or_else(|err| die(format!("Worker thread panicked: {:?}", err)))
           .unwrap_or_else(|err| die(format!("Worker thread died: {:?}", err)));
 
-    remove_file(&sock_path)
+    PlatformTransport::cleanup(&sock_path)
         .unwrap_or_else(|err| die(format!("Could not remove socket: {}", err)));
 
     match wait_result {
@@ -0,0 +1,43 @@
+use std::io;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+use windows_sys::Win32::Foundation::{CloseHandle, BOOL};
+use windows_sys::Win32::System::Console::{
+    SetConsoleCtrlHandler, CTRL_BREAK_EVENT, CTRL_CLOSE_EVENT, CTRL_C_EVENT,
+};
+use windows_sys::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+/// Stores the process ID of the child we spawned, so the console control
+/// handler can terminate it from a context where it can't meaningfully
+/// return an error or do any logging.
+static CHILD_PID: AtomicU32 = AtomicU32::new(0);
+
+unsafe extern "system" fn on_ctrl_event(ctrl_type: u32) -> BOOL {
+    match ctrl_type {
+        CTRL_C_EVENT | CTRL_CLOSE_EVENT | CTRL_BREAK_EVENT => {
+            let pid = CHILD_PID.load(Ordering::SeqCst);
+            if pid != 0 {
+                let handle = unsafe { OpenProcess(PROCESS_TERMINATE, 0, pid) };
+                if handle != 0 {
+                    unsafe {
+                        TerminateProcess(handle, 1);
+                        CloseHandle(handle);
+                    }
+                }
+            }
+            1
+        }
+        _ => 0,
+    }
+}
+
+/// Registers a console control handler that terminates the given process
+/// when *this* process receives CTRL_C, CTRL_BREAK, or the console closing.
+pub fn kill_child_on_signal(child_pid: i32) -> io::Result<()> {
+    CHILD_PID.store(child_pid as u32, Ordering::SeqCst);
+
+    if unsafe { SetConsoleCtrlHandler(Some(on_ctrl_event), 1) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Platform-independent pieces of the socket/pipe bridge: a listener that
+//! accepts bidirectional streams, and a readiness selector that reports when
+//! a registered handle can be read from or written to.
+//!
+//! Unix builds implement this directly on top of epoll and `UnixListener`/
+//! `UnixStream`. Windows builds implement it on top of a named pipe and
+//! overlapped I/O, synthesizing the same readiness semantics so that
+//! `socket_stream_bridge` doesn't need to know which platform it's on.
+
+use std::io;
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::{UnixTransport as PlatformTransport};
+
+#[cfg(windows)]
+mod windows;
+#[cfg(windows)]
+pub use windows::{PipeTransport as PlatformTransport};
+
+/// Opaque identifier a [`Selector`] uses to say which registered handle an
+/// event belongs to. Plays the same role as an epoll `data` field or an IOCP
+/// completion key.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash)]
+pub struct Token(pub u64);
+
+/// Implemented by anything that can be registered with a [`Selector`]: the
+/// listener, an accepted stream, or one of the child process's pipes.
+pub trait Registrable {
+    /// A stable identifier for this handle, used as the `Token` under which
+    /// it's registered.
+    fn token(&self) -> Token;
+}
+
+/// Which direction(s) of I/O a registration should be woken up for.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Interest {
+    pub readable: bool,
+    pub writable: bool,
+}
+
+impl Interest {
+    pub const NONE: Interest = Interest { readable: false, writable: false };
+    pub const READABLE: Interest = Interest { readable: true, writable: false };
+
+    /// Returns a copy of this interest with the writable half set to
+    /// `writable`, used to toggle `EPOLLOUT`-style backpressure interest
+    /// without disturbing the readable half.
+    pub fn with_writable(self, writable: bool) -> Interest {
+        Interest { writable, ..self }
+    }
+}
+
+/// One readiness notification returned from [`Selector::wait`].
+#[derive(Clone, Copy, Debug)]
+pub struct Ready {
+    pub token: Token,
+    pub readable: bool,
+    pub writable: bool,
+    /// The peer half- or fully closed the connection (`EPOLLRDHUP`/
+    /// `EPOLLHUP`, or the Windows pipe equivalent).
+    pub hangup: bool,
+}
+
+/// A readiness-based event source. Register handles with it, then block in
+/// [`wait`](Selector::wait) until one or more of them are ready.
+pub trait Selector: Sized {
+    fn create() -> io::Result<Self>;
+
+    fn register<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()>;
+    fn modify<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()>;
+    fn deregister<R: Registrable>(&self, target: &R) -> io::Result<()>;
+
+    /// Blocks until at least one registered handle is ready, appending the
+    /// resulting events to `out`.
+    fn wait(&self, out: &mut Vec<Ready>) -> io::Result<()>;
+}
+
+/// A connected, bidirectional client of the bridge: a `UnixStream` on Unix,
+/// one end of a named pipe instance on Windows.
+pub trait TransportStream: io::Read + io::Write + Registrable {
+    /// Re-arms whatever edge-triggered I/O this stream relies on, called by
+    /// the bridge right after it has drained a readable event for it.
+    ///
+    /// Unix's epoll registrations are level-triggered, so `UnixStream`
+    /// leaves this as a no-op; Windows named pipes need a fresh overlapped
+    /// `ReadFile` posted so the completion port sees the next arrival.
+    fn rearm(&self) {}
+}
+
+/// Accepts new [`TransportStream`]s for a [`Transport`].
+pub trait TransportListener: Registrable {
+    type Stream: TransportStream;
+
+    /// Accepts one pending connection. Non-blocking: returns
+    /// `io::ErrorKind::WouldBlock` if none is pending.
+    fn accept(&self) -> io::Result<Self::Stream>;
+}
+
+/// Ties a listener, its accepted stream type, and a selector together for
+/// one platform.
+pub trait Transport {
+    type Listener: TransportListener<Stream = Self::Stream>;
+    type Stream: TransportStream;
+    type Selector: Selector;
+
+    /// Starts listening at `path` (a filesystem path for the Unix socket, or
+    /// a pipe name for the Windows backend), ready for non-blocking accepts.
+    fn bind(path: &str) -> io::Result<Self::Listener>;
+
+    /// Removes whatever `bind` created at `path`, once the bridge is done
+    /// with it.
+    fn cleanup(path: &str) -> io::Result<()>;
+}
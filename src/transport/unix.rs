@@ -0,0 +1,125 @@
+use std::fs::remove_file;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use epoll::{ControlOptions, Event, Events};
+
+use super::{Interest, Ready, Registrable, Selector, Token, Transport, TransportListener, TransportStream};
+
+impl<T: AsRawFd> Registrable for T {
+    fn token(&self) -> Token {
+        Token(self.as_raw_fd() as u64)
+    }
+}
+
+impl TransportStream for UnixStream {}
+
+impl TransportListener for UnixListener {
+    type Stream = UnixStream;
+
+    fn accept(&self) -> io::Result<UnixStream> {
+        let (stream, _) = UnixListener::accept(self)?;
+        stream.set_nonblocking(true)?;
+        Ok(stream)
+    }
+}
+
+/// The Unix backend: an epoll instance used as the readiness [`Selector`],
+/// a plain Unix domain socket as the [`Transport`].
+pub struct UnixTransport;
+
+impl Transport for UnixTransport {
+    type Listener = UnixListener;
+    type Stream = UnixStream;
+    type Selector = EpollSelector;
+
+    fn bind(path: &str) -> io::Result<UnixListener> {
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        Ok(listener)
+    }
+
+    fn cleanup(path: &str) -> io::Result<()> {
+        remove_file(path)
+    }
+}
+
+fn to_epoll_events(interest: Interest) -> Events {
+    let mut events = Events::EPOLLRDHUP;
+    if interest.readable {
+        events |= Events::EPOLLIN;
+    }
+    if interest.writable {
+        events |= Events::EPOLLOUT;
+    }
+    events
+}
+
+pub struct EpollSelector {
+    epfd: i32,
+}
+
+impl AsRawFd for EpollSelector {
+    fn as_raw_fd(&self) -> i32 {
+        self.epfd
+    }
+}
+
+impl Selector for EpollSelector {
+    fn create() -> io::Result<Self> {
+        Ok(EpollSelector { epfd: epoll::create(true)? })
+    }
+
+    fn register<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()> {
+        let token = target.token();
+        epoll::ctl(
+            self.epfd,
+            ControlOptions::EPOLL_CTL_ADD,
+            token.0 as i32,
+            Event::new(to_epoll_events(interest), token.0),
+        )
+    }
+
+    fn modify<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()> {
+        let token = target.token();
+        epoll::ctl(
+            self.epfd,
+            ControlOptions::EPOLL_CTL_MOD,
+            token.0 as i32,
+            Event::new(to_epoll_events(interest), token.0),
+        )
+    }
+
+    fn deregister<R: Registrable>(&self, target: &R) -> io::Result<()> {
+        let token = target.token();
+        epoll::ctl(
+            self.epfd,
+            ControlOptions::EPOLL_CTL_DEL,
+            token.0 as i32,
+            Event::new(Events::empty(), 0),
+        )
+    }
+
+    fn wait(&self, out: &mut Vec<Ready>) -> io::Result<()> {
+        let mut buf = [Event::new(Events::empty(), 0); 8];
+        let count = epoll::wait(self.epfd, -1, &mut buf)?;
+        for event in &buf[..count] {
+            let flags = Events::from_bits_truncate(event.events);
+            out.push(Ready {
+                token: Token(event.data),
+                readable: flags.contains(Events::EPOLLIN),
+                writable: flags.contains(Events::EPOLLOUT),
+                // EPOLLHUP and EPOLLERR are reported by the kernel whenever
+                // they apply, regardless of what was requested in the
+                // interest mask (unlike EPOLLRDHUP, which we do request) —
+                // e.g. a pipe whose only reader has closed shows up as
+                // EPOLLERR/EPOLLHUP on the writer's side.
+                hangup: flags.contains(Events::EPOLLRDHUP)
+                    || flags.contains(Events::EPOLLHUP)
+                    || flags.contains(Events::EPOLLERR),
+            });
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+//! Named-pipe backend. Named pipes don't expose readiness the way a socket
+//! does, so each stream keeps an overlapped read permanently in flight and
+//! a small buffer of whatever it completed with; the I/O completion port
+//! plays the role epoll plays on Unix, and [`PipeSelector::wait`] turns its
+//! completions back into the same readable/writable/hangup notifications
+//! the rest of the bridge expects.
+
+use std::cell::{Cell, RefCell};
+use std::io::{self, Read, Write};
+use std::ptr::null_mut;
+
+use windows_sys::Win32::Foundation::{
+    CloseHandle, GetLastError, ERROR_IO_PENDING, ERROR_PIPE_CONNECTED, HANDLE, INVALID_HANDLE_VALUE,
+};
+use windows_sys::Win32::Storage::FileSystem::{ReadFile, WriteFile};
+use windows_sys::Win32::System::Pipes::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe};
+use windows_sys::Win32::System::IO::{
+    CreateIoCompletionPort, GetQueuedCompletionStatus, OVERLAPPED,
+};
+use windows_sys::Win32::System::Pipes::{
+    PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_OVERLAPPED;
+
+use super::{Interest, Ready, Registrable, Selector, Token, Transport, TransportListener, TransportStream};
+
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Covers the child process's stdin/stdout/stderr handles as well, so the
+/// bridge can register them with [`PipeSelector`] the same way it registers
+/// a [`PipeStream`]. Note that `Stdio::piped()` hands back plain
+/// (non-overlapped) pipes, so full asynchronous child I/O on Windows is a
+/// follow-up; this makes the types line up for when that lands.
+impl<T: std::os::windows::io::AsRawHandle> Registrable for T {
+    fn token(&self) -> Token {
+        Token(self.as_raw_handle() as u64)
+    }
+}
+
+/// Tokens are the handle value itself, matching the IOCP completion key we
+/// hand back from `GetQueuedCompletionStatus`.
+fn handle_token(handle: HANDLE) -> Token {
+    Token(handle as u64)
+}
+
+/// Distinguishes what kind of overlapped operation an `OVERLAPPED` pointer
+/// handed back by `GetQueuedCompletionStatus` belongs to. Reads and writes
+/// on the same duplex pipe handle complete through the same I/O completion
+/// port and share a token, so the pointer itself is the only thing that
+/// tells a read's completion apart from a write's.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OverlappedKind {
+    Read,
+    Write,
+}
+
+/// Common header every overlapped operation's allocation starts with.
+/// `overlapped` must stay the first field: `GetQueuedCompletionStatus` hands
+/// back the exact address that was passed to `ReadFile`/`WriteFile`, so
+/// casting that pointer back to `*const OverlappedHeader` is only sound
+/// because this layout lines up with it.
+#[repr(C)]
+struct OverlappedHeader {
+    overlapped: OVERLAPPED,
+    kind: OverlappedKind,
+}
+
+/// One in-flight (or just-completed) overlapped `ReadFile`'s completion
+/// state. `header` being `#[repr(C)]`'s first field lets [`PipeSelector::wait`]
+/// cast the raw `OVERLAPPED*` it gets back straight back to `*const
+/// ReadCompletion` and fill in what the read actually produced, without
+/// needing to reach through the owning [`PipeStream`].
+#[repr(C)]
+struct ReadCompletion {
+    header: OverlappedHeader,
+    done: Cell<bool>,
+    bytes: Cell<u32>,
+    hangup: Cell<bool>,
+}
+
+impl ReadCompletion {
+    fn new() -> Box<Self> {
+        Box::new(ReadCompletion {
+            header: OverlappedHeader { overlapped: unsafe { std::mem::zeroed() }, kind: OverlappedKind::Read },
+            done: Cell::new(false),
+            bytes: Cell::new(0),
+            hangup: Cell::new(false),
+        })
+    }
+}
+
+/// One in-flight overlapped `WriteFile`'s header. [`PipeStream::write`] only
+/// expects near-immediate completion and doesn't track pending writes past
+/// that call, so this is intentionally leaked on every call, whether or not
+/// `WriteFile` returns `ERROR_IO_PENDING`: its only job is to give the completion
+/// packet a stable, correctly-tagged `OVERLAPPED` to land on instead of a
+/// dangling stack pointer.
+#[repr(C)]
+struct WriteCompletion {
+    header: OverlappedHeader,
+}
+
+/// One in-flight (or just-completed) overlapped `ReadFile`, plus whatever
+/// bytes it produced that `read()` hasn't handed out yet.
+struct PendingRead {
+    completion: Box<ReadCompletion>,
+    buf: Box<[u8; READ_CHUNK]>,
+    in_flight: bool,
+    ready_len: usize,
+    ready_pos: usize,
+    hangup: bool,
+}
+
+impl PendingRead {
+    fn new() -> Self {
+        PendingRead {
+            completion: ReadCompletion::new(),
+            buf: Box::new([0u8; READ_CHUNK]),
+            in_flight: false,
+            ready_len: 0,
+            ready_pos: 0,
+            hangup: false,
+        }
+    }
+}
+
+/// One end of a named pipe, handed to `socket_stream_bridge` as a client
+/// stream. The child's stdin/stdout/stderr are not wrapped in this type;
+/// they are registered through the blanket `Registrable` impl above.
+pub struct PipeStream {
+    handle: HANDLE,
+    read: RefCell<PendingRead>,
+}
+
+unsafe impl Send for PipeStream {}
+
+impl Drop for PipeStream {
+    fn drop(&mut self) {
+        unsafe {
+            DisconnectNamedPipe(self.handle);
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+impl Registrable for PipeStream {
+    fn token(&self) -> Token {
+        handle_token(self.handle)
+    }
+}
+
+impl TransportStream for PipeStream {
+    fn rearm(&self) {
+        pump_reads(self);
+    }
+}
+
+impl Read for PipeStream {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        let mut pending = self.read.borrow_mut();
+        if pending.ready_pos < pending.ready_len {
+            let n = (pending.ready_len - pending.ready_pos).min(out.len());
+            out[..n].copy_from_slice(&pending.buf[pending.ready_pos..pending.ready_pos + n]);
+            pending.ready_pos += n;
+            return Ok(n);
+        }
+        if pending.hangup {
+            return Ok(0);
+        }
+        Err(io::Error::from(io::ErrorKind::WouldBlock))
+    }
+}
+
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Named pipe writes are issued synchronously from the bridge's
+        // perspective: WriteFile either completes immediately (the common
+        // case for the small writes the bridge does) or we surface
+        // WouldBlock and let the caller retry once the pipe drains.
+        let completion = Box::leak(Box::new(WriteCompletion {
+            header: OverlappedHeader { overlapped: unsafe { std::mem::zeroed() }, kind: OverlappedKind::Write },
+        }));
+        let mut written: u32 = 0;
+        let ok = unsafe {
+            WriteFile(
+                self.handle,
+                buf.as_ptr(),
+                buf.len() as u32,
+                &mut written,
+                &mut completion.header.overlapped,
+            )
+        };
+        if ok == 0 {
+            let err = unsafe { GetLastError() };
+            if err == ERROR_IO_PENDING {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            return Err(io::Error::from_raw_os_error(err as i32));
+        }
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Starts a fresh overlapped `ReadFile` on `pending` if one isn't already in
+/// flight and the caller has consumed the last completion. If a read
+/// was in flight and `PipeSelector::wait` has since recorded its result,
+/// pulls that result into `pending` first.
+fn arm_read(handle: HANDLE, pending: &mut PendingRead) {
+    if pending.in_flight {
+        if !pending.completion.done.get() {
+            // Still waiting on the kernel; nothing to reconcile yet.
+            return;
+        }
+        pending.ready_pos = 0;
+        pending.ready_len = pending.completion.bytes.get() as usize;
+        pending.hangup = pending.completion.hangup.get();
+        pending.in_flight = false;
+        pending.completion.done.set(false);
+    }
+    if pending.ready_pos < pending.ready_len || pending.hangup {
+        // The caller hasn't consumed the last completion yet, or the peer's
+        // gone and there's no point posting another read.
+        return;
+    }
+    let mut read: u32 = 0;
+    let ok = unsafe {
+        ReadFile(
+            handle,
+            pending.buf.as_mut_ptr(),
+            pending.buf.len() as u32,
+            &mut read,
+            &mut pending.completion.header.overlapped,
+        )
+    };
+    if ok != 0 {
+        // Completed synchronously; the completion port still posts an entry
+        // for it, so just mark it in flight and let `wait` pick it up.
+    }
+    pending.in_flight = true;
+}
+
+pub struct PipeListener {
+    handle: Cell<HANDLE>,
+    path: Box<[u16]>,
+}
+
+unsafe impl Send for PipeListener {}
+
+impl Registrable for PipeListener {
+    fn token(&self) -> Token {
+        handle_token(self.handle.get())
+    }
+}
+
+impl TransportListener for PipeListener {
+    type Stream = PipeStream;
+
+    fn accept(&self) -> io::Result<PipeStream> {
+        let current = self.handle.get();
+        let mut overlapped: OVERLAPPED = unsafe { std::mem::zeroed() };
+        let ok = unsafe { ConnectNamedPipe(current, &mut overlapped) };
+        if ok == 0 {
+            let err = unsafe { GetLastError() };
+            if err == ERROR_IO_PENDING {
+                return Err(io::Error::from(io::ErrorKind::WouldBlock));
+            }
+            if err != ERROR_PIPE_CONNECTED {
+                return Err(io::Error::from_raw_os_error(err as i32));
+            }
+        }
+
+        // `self` keeps listening for the *next* client on a fresh pipe
+        // instance; the one that just connected is handed off to the
+        // bridge as a regular stream.
+        let next = create_pipe_instance(&self.path)?;
+        self.handle.set(next);
+        let stream = PipeStream { handle: current, read: RefCell::new(PendingRead::new()) };
+        // Post the first overlapped ReadFile right away; the bridge only
+        // calls TransportStream::rearm() after it has drained a completion,
+        // so without this the first read would never be in flight.
+        pump_reads(&stream);
+        Ok(stream)
+    }
+}
+
+fn create_pipe_instance(path: &[u16]) -> io::Result<HANDLE> {
+    let open_mode = PIPE_ACCESS_DUPLEX | FILE_FLAG_OVERLAPPED;
+    let handle = unsafe {
+        CreateNamedPipeW(
+            path.as_ptr(),
+            open_mode,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT,
+            PIPE_UNLIMITED_INSTANCES,
+            64 * 1024,
+            64 * 1024,
+            0,
+            null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(handle)
+}
+
+fn to_wide(path: &str) -> Box<[u16]> {
+    path.encode_utf16().chain(std::iter::once(0)).collect::<Vec<u16>>().into_boxed_slice()
+}
+
+/// The Windows backend: a named pipe (`\\.\pipe\...`) as the [`Transport`],
+/// an I/O completion port as the [`Selector`].
+pub struct PipeTransport;
+
+impl Transport for PipeTransport {
+    type Listener = PipeListener;
+    type Stream = PipeStream;
+    type Selector = PipeSelector;
+
+    fn bind(path: &str) -> io::Result<PipeListener> {
+        let wide = to_wide(path);
+        let handle = create_pipe_instance(&wide)?;
+        Ok(PipeListener { handle, path: wide })
+    }
+
+    fn cleanup(_path: &str) -> io::Result<()> {
+        // Named pipe instances are cleaned up as their handles are dropped;
+        // there's no filesystem entry to remove like the Unix socket path.
+        Ok(())
+    }
+}
+
+pub struct PipeSelector {
+    port: HANDLE,
+    // Read-side interest is driven by keeping an overlapped read in flight;
+    // this just remembers which handles want write readiness reported so
+    // `wait` knows to synthesize it.
+    want_write: RefCell<Vec<(HANDLE, bool)>>,
+}
+
+unsafe impl Send for PipeSelector {}
+unsafe impl Sync for PipeSelector {}
+
+impl PipeSelector {
+    fn set_want_write(&self, handle: HANDLE, want: bool) {
+        let mut list = self.want_write.borrow_mut();
+        if let Some(entry) = list.iter_mut().find(|(h, _)| *h == handle) {
+            entry.1 = want;
+        } else {
+            list.push((handle, want));
+        }
+    }
+}
+
+impl Selector for PipeSelector {
+    fn create() -> io::Result<Self> {
+        let port = unsafe { CreateIoCompletionPort(INVALID_HANDLE_VALUE, 0, 0, 0) };
+        if port == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(PipeSelector { port, want_write: RefCell::new(Vec::new()) })
+    }
+
+    fn register<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()> {
+        let token = target.token();
+        let handle = token.0 as HANDLE;
+        let key = unsafe { CreateIoCompletionPort(handle, self.port, token.0 as usize, 0) };
+        if key == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.set_want_write(handle, interest.writable);
+        Ok(())
+    }
+
+    fn modify<R: Registrable>(&self, target: &R, interest: Interest) -> io::Result<()> {
+        self.set_want_write(target.token().0 as HANDLE, interest.writable);
+        Ok(())
+    }
+
+    fn deregister<R: Registrable>(&self, target: &R) -> io::Result<()> {
+        let handle = target.token().0 as HANDLE;
+        self.want_write.borrow_mut().retain(|(h, _)| *h != handle);
+        Ok(())
+    }
+
+    fn wait(&self, out: &mut Vec<Ready>) -> io::Result<()> {
+        let mut bytes: u32 = 0;
+        let mut key: usize = 0;
+        let mut overlapped_ptr: *mut OVERLAPPED = null_mut();
+        let ok = unsafe {
+            GetQueuedCompletionStatus(self.port, &mut bytes, &mut key, &mut overlapped_ptr, u32::MAX)
+        };
+        let hangup = ok == 0 && !overlapped_ptr.is_null();
+        let token = Token(key as u64);
+
+        if !overlapped_ptr.is_null() {
+            // `overlapped` is the first field of OverlappedHeader
+            // (#[repr(C)]), so this pointer is also a valid pointer to its
+            // surrounding header; check the tag before trusting it's a read
+            // completion, since a write's completion shares the same
+            // handle/token and would otherwise be misread as one.
+            let header = overlapped_ptr as *const OverlappedHeader;
+            if unsafe { (*header).kind } == OverlappedKind::Read {
+                let completion = overlapped_ptr as *const ReadCompletion;
+                unsafe {
+                    (*completion).bytes.set(bytes);
+                    (*completion).hangup.set(hangup);
+                    (*completion).done.set(true);
+                }
+            }
+        }
+
+        out.push(Ready {
+            token,
+            readable: bytes > 0 || hangup,
+            writable: self.want_write.borrow().iter().any(|(h, w)| *w && *h as u64 == token.0),
+            hangup,
+        });
+        Ok(())
+    }
+}
+
+/// Re-arms the overlapped read for `stream` if the bridge still wants to
+/// hear about new input on it. Called from the event loop right after a
+/// readable event is drained, mirroring how the Unix side just leaves
+/// `EPOLLIN` permanently armed.
+pub fn pump_reads(stream: &PipeStream) {
+    let mut pending = stream.read.borrow_mut();
+    arm_read(stream.handle, &mut pending);
+}